@@ -1,27 +1,83 @@
-use std::ops::{Index, IndexMut, Deref, DerefMut};
-use std::os::raw::c_void;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-extern "C" {
-    fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
-    fn free(ptr: *mut c_void);
+extern crate alloc;
+
+use core::mem::MaybeUninit;
+use core::ops::{Bound, Index, IndexMut, Deref, DerefMut, RangeBounds};
+
+// The actual `alloc`/`realloc`/`dealloc` calls are routed through this tiny
+// module so the rest of the crate doesn't care which allocator backs it.
+// The default, `no_std`-friendly path goes through `alloc::alloc` (i.e.
+// whatever `#[global_allocator]` the final binary registers). The `libc`
+// feature swaps in the raw C `realloc`/`free` this crate used to hard-code,
+// for callers that need the FFI-compatible layout.
+#[cfg(not(feature = "libc"))]
+mod raw_alloc {
+    use core::alloc::Layout;
+
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        alloc::alloc::alloc(layout)
+    }
+
+    pub unsafe fn realloc(ptr: *mut u8, old_layout: Layout, new_size: usize) -> *mut u8 {
+        alloc::alloc::realloc(ptr, old_layout, new_size)
+    }
+
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        alloc::alloc::dealloc(ptr, layout)
+    }
+}
+
+#[cfg(feature = "libc")]
+mod raw_alloc {
+    use core::alloc::Layout;
+    use core::ffi::c_void;
+
+    extern "C" {
+        #[link_name = "realloc"]
+        fn c_realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
+        #[link_name = "free"]
+        fn c_free(ptr: *mut c_void);
+    }
+
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        c_realloc(core::ptr::null_mut(), layout.size()) as *mut u8
+    }
+
+    pub unsafe fn realloc(ptr: *mut u8, _old_layout: Layout, new_size: usize) -> *mut u8 {
+        c_realloc(ptr as *mut c_void, new_size) as *mut u8
+    }
+
+    pub unsafe fn dealloc(ptr: *mut u8, _layout: Layout) {
+        c_free(ptr as *mut c_void)
+    }
 }
 
 #[derive(Debug)]
 struct Unique<T> {
     ptr: *const T,
-    _marker: std::marker::PhantomData<T>
+    _marker: core::marker::PhantomData<T>
 }
 
 impl<T> Unique<T> {
     fn new(ptr: *mut T) -> Unique<T> {
         Unique {
-            ptr: ptr,
-            _marker: std::marker::PhantomData
+            ptr,
+            _marker: core::marker::PhantomData
         }
     }
 
+    // A non-null, well-aligned pointer that is never dereferenced. Used as the
+    // backing store both for zero-sized `T`, where there's no allocation to
+    // point at, and for a non-ZST `Vec` with no allocation yet (`cap == 0`):
+    // `core::ptr::null_mut()` would violate the non-null precondition of
+    // `slice::from_raw_parts[_mut]` and `ptr::write_bytes` even at length 0.
     fn empty() -> Unique<T> {
-        Unique::new(std::ptr::null_mut())
+        Unique::dangling()
+    }
+
+    fn dangling() -> Unique<T> {
+        Unique::new(core::ptr::dangling_mut::<T>())
     }
 
     fn as_ptr(&self) -> *mut T {
@@ -29,6 +85,33 @@ impl<T> Unique<T> {
     }
 }
 
+/// Why a fallible allocation in [`Vec`] failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The required capacity, in bytes, would exceed `isize::MAX` before the
+    /// allocator is even asked (for a zero-sized `T`: the required length
+    /// would exceed `isize::MAX` elements).
+    CapacityOverflow,
+    /// The allocator refused a request for this many bytes.
+    AllocError { requested_bytes: usize },
+}
+
+impl core::fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            TryReserveError::CapacityOverflow => {
+                write!(f, "required capacity exceeds isize::MAX bytes")
+            }
+            TryReserveError::AllocError { requested_bytes } => {
+                write!(f, "allocator failed to allocate {} bytes", requested_bytes)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryReserveError {}
+
 #[derive(Debug)]
 pub struct Vec<T> {
     ptr: Unique<T>,
@@ -37,11 +120,26 @@ pub struct Vec<T> {
 }
 
 impl<T> Vec<T> {
+    // Zero-sized types need no allocation at all: every instance of `T` takes
+    // no space, so the vector can claim effectively infinite capacity up front
+    // and never touch the allocator.
+    fn is_zst() -> bool {
+        core::mem::size_of::<T>() == 0
+    }
+
     pub fn new() -> Vec<T> {
-        Vec {
-            ptr: Unique::empty(),
-            cap: 0,
-            len: 0
+        if Self::is_zst() {
+            Vec {
+                ptr: Unique::dangling(),
+                cap: usize::MAX,
+                len: 0
+            }
+        } else {
+            Vec {
+                ptr: Unique::empty(),
+                cap: 0,
+                len: 0
+            }
         }
     }
 
@@ -49,13 +147,11 @@ impl<T> Vec<T> {
         self.len
     }
 
-    pub fn push(&mut self, value: T) -> Result<(), ()> {
-        if self.len == self.cap {
-            self.resize()?;
-        }
+    pub fn push(&mut self, value: T) -> Result<(), TryReserveError> {
+        self.grow_for_one()?;
         unsafe {
-            let end = self.ptr.as_ptr().offset(self.len as isize);
-            std::ptr::write(end, value);
+            let end = self.ptr.as_ptr().add(self.len);
+            core::ptr::write(end, value);
             self.len += 1;
         }
         Ok(())
@@ -69,9 +165,9 @@ impl<T> Vec<T> {
         self
     }
 
-    pub fn append(&mut self, other: &mut Vec<T>) -> Result<(), ()> {
+    pub fn append(&mut self, other: &mut Vec<T>) -> Result<(), TryReserveError> {
         let mut mine = Vec::new();
-        std::mem::swap(&mut mine, other);
+        core::mem::swap(&mut mine, other);
         self.reserve(mine.len())?;
         for elem in mine.into_iter() {
             self.push(elem)?;
@@ -84,14 +180,26 @@ impl<T> Vec<T> {
     }
 
     pub fn clear(&mut self) {
-        while let Some(_) = self.pop() {}
+        while self.pop().is_some() {}
         debug_assert_eq!(self.len, 0);
     }
 
-    pub fn reserve(&mut self, additional: usize) -> Result<(), ()> {
-        self.cap = std::cmp::max(1, self.cap + additional) - 1;
-        self.resize()?;
-        Ok(())
+    pub fn reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self.len.checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if Self::is_zst() {
+            // No storage to grow; just make sure the new length stays representable.
+            return if required <= isize::MAX as usize {
+                Ok(())
+            } else {
+                Err(TryReserveError::CapacityOverflow)
+            };
+        }
+        if required <= self.cap {
+            return Ok(());
+        }
+        let new_cap = required.checked_next_power_of_two().unwrap_or(required);
+        self.set_cap(new_cap)
     }
 
     fn pop(&mut self) -> Option<T> {
@@ -100,9 +208,69 @@ impl<T> Vec<T> {
         } else {
             unsafe {
                 self.len -= 1;
-                Some(std::ptr::read(self.ptr.as_ptr().offset(self.len as isize)))
+                Some(core::ptr::read(self.ptr.as_ptr().add(self.len)))
+            }
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), TryReserveError> {
+        assert!(index <= self.len, "index out of bounds");
+        self.grow_for_one()?;
+        unsafe {
+            let p = self.ptr.as_ptr().add(index);
+            if index < self.len {
+                core::ptr::copy(p, p.offset(1), self.len - index);
+            }
+            core::ptr::write(p, value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds");
+        unsafe {
+            self.len -= 1;
+            let p = self.ptr.as_ptr().add(index);
+            let result = core::ptr::read(p);
+            core::ptr::copy(p.offset(1), p, self.len - index);
+            result
+        }
+    }
+
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds");
+        unsafe {
+            self.len -= 1;
+            let base = self.ptr.as_ptr();
+            let last = base.add(self.len);
+            core::ptr::swap(base.add(index), last);
+            core::ptr::read(last)
+        }
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(&T) -> bool {
+        let len = self.len;
+        let mut del = 0;
+        {
+            let v = self.as_mut_slice();
+            for i in 0..len {
+                if !f(&v[i]) {
+                    del += 1;
+                } else if del > 0 {
+                    v.swap(i - del, i);
+                }
             }
         }
+        if del > 0 {
+            self.truncate(len - del);
+        }
     }
 
     /*
@@ -110,50 +278,201 @@ impl<T> Vec<T> {
      */
 
     // Like with_capacity, but len set and data initialized.
-    pub fn with_len(size: usize) -> Result<Vec<T>, ()> {
-        let mut vec = Vec {
-            ptr: Unique::empty(),
-            cap: std::cmp::max(1, size) - 1,
-            len: 0,
-        };
-        vec.resize()?;
+    pub fn with_len(size: usize) -> Result<Vec<T>, TryReserveError> {
+        let mut vec = Vec::new();
+        if Self::is_zst() {
+            if size > isize::MAX as usize {
+                return Err(TryReserveError::CapacityOverflow);
+            }
+        } else {
+            vec.set_cap(size)?;
+        }
+        unsafe {
+            core::ptr::write_bytes(vec.ptr.as_ptr(), 0, size);
+        }
         vec.len = size;
         Ok(vec)
     }
 
-    fn resize(&mut self) -> Result<(), ()> {
-        let elem_size = std::mem::size_of::<T>();
-        let new_cap = self.cap + 1; // XXX: double or something
+    // Ensures room for one more element, sharing the ZST short-circuit between
+    // `push`, `insert`, and `extend`.
+    fn grow_for_one(&mut self) -> Result<(), TryReserveError> {
+        if Self::is_zst() {
+            // `cap` is already usize::MAX; just guard the length itself against
+            // overflowing the same isize::MAX limit real allocations respect.
+            if self.len == isize::MAX as usize {
+                return Err(TryReserveError::CapacityOverflow);
+            }
+            Ok(())
+        } else if self.len == self.cap {
+            self.grow()
+        } else {
+            Ok(())
+        }
+    }
+
+    // Doubles capacity (matching the amortized growth real Vec relies on) rather
+    // than growing by exactly one element at a time. Never called for ZSTs,
+    // which report usize::MAX capacity and so never hit the grow path.
+    fn grow(&mut self) -> Result<(), TryReserveError> {
+        let new_cap = if self.cap == 0 {
+            1
+        } else {
+            self.cap.checked_mul(2).ok_or(TryReserveError::CapacityOverflow)?
+        };
+        self.set_cap(new_cap)
+    }
+
+    // Reallocates the backing storage to hold exactly `new_cap` elements.
+    // `Layout::array` computes the byte size and rejects anything that would
+    // overflow the `isize::MAX` limit real allocations respect.
+    fn set_cap(&mut self, new_cap: usize) -> Result<(), TryReserveError> {
+        if Self::is_zst() {
+            // Zero-sized types never need heap storage; see `Vec::new`.
+            self.cap = new_cap;
+            return Ok(());
+        }
+        let new_layout = core::alloc::Layout::array::<T>(new_cap)
+            .map_err(|_| TryReserveError::CapacityOverflow)?;
+        let new_size = new_layout.size();
+        if new_size == 0 {
+            // `GlobalAlloc::alloc`/`realloc` require a non-zero size. There's
+            // nothing to allocate for zero capacity, so free any existing
+            // allocation (this only happens via `with_len(0)`, since `grow`
+            // never requests a zero-sized allocation itself) and just update
+            // the bookkeeping, exactly like the ZST branch above.
+            if self.cap != 0 {
+                unsafe {
+                    let old_layout = core::alloc::Layout::array::<T>(self.cap).unwrap();
+                    raw_alloc::dealloc(self.ptr.as_ptr() as *mut u8, old_layout);
+                }
+                self.ptr = Unique::empty();
+            }
+            self.cap = 0;
+            return Ok(());
+        }
         unsafe {
-            let ptr = realloc(self.ptr.as_ptr() as *mut _, new_cap * elem_size) as *mut T;
-            if ptr.is_null() {
-                return Err(());
+            let raw_ptr = if self.cap == 0 {
+                raw_alloc::alloc(new_layout)
+            } else {
+                let old_layout = core::alloc::Layout::array::<T>(self.cap).unwrap();
+                raw_alloc::realloc(self.ptr.as_ptr() as *mut u8, old_layout, new_size)
+            };
+            if raw_ptr.is_null() {
+                return Err(TryReserveError::AllocError { requested_bytes: new_size });
             }
-            std::ptr::write_bytes(ptr.offset(self.len as isize), 0, new_cap - self.cap);
-            self.ptr = Unique::new(ptr);
+            self.ptr = Unique::new(raw_ptr as *mut T);
             self.cap = new_cap;
         }
         Ok(())
     }
+
+    // Unlike `with_len` above, `spare_capacity_mut`, `set_len`, `extend` and
+    // `drain` below follow the Vec API (with `extend` returning a `Result`).
+
+    // The uninitialized tail of the backing storage, from `len` to `cap`.
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        unsafe {
+            core::slice::from_raw_parts_mut(
+                self.ptr.as_ptr().add(self.len) as *mut MaybeUninit<T>,
+                self.cap - self.len,
+            )
+        }
+    }
+
+    /// Sets the length without initializing or dropping anything.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure the first `new_len` elements are actually
+    /// initialized, and that `new_len <= self.cap`.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.cap);
+        self.len = new_len;
+    }
+
+    pub fn extend<I>(&mut self, iter: I) -> Result<(), TryReserveError>
+        where I: IntoIterator<Item = T>
+    {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.reserve(lower)?;
+        for value in iter {
+            self.grow_for_one()?;
+            self.spare_capacity_mut()[0].write(value);
+            unsafe {
+                self.set_len(self.len + 1);
+            }
+        }
+        Ok(())
+    }
+
+    // Removes the elements in `range`, yielding them by value and closing the
+    // gap they leave behind once the returned `Drain` is dropped.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T> where R: RangeBounds<usize> {
+        let len = self.len;
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n + 1,
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => len,
+        };
+        assert!(start <= end, "drain start must not exceed end");
+        assert!(end <= len, "drain range out of bounds");
+
+        // Shrink the vector to the front of the drained range right away: if
+        // the returned Drain is leaked, the vector still never exposes
+        // duplicated or half-moved elements, just fewer than it logically has.
+        self.len = start;
+
+        let base = self.ptr.as_ptr();
+        // As in `IntoIter`, a ZST's `offset` always adds zero bytes regardless
+        // of count, so `iter`/`end` would collapse onto the same address and
+        // the drain would silently yield nothing. Treat the pointer as a
+        // plain element counter instead, matching `Drain::next` below.
+        let (iter, iter_end) = if Self::is_zst() {
+            (base as *const T, (base as usize + (end - start)) as *const T)
+        } else {
+            unsafe {
+                (base.add(start) as *const T, base.add(end) as *const T)
+            }
+        };
+
+        Drain {
+            vec: self,
+            iter,
+            end: iter_end,
+            tail_start: end,
+            tail_len: len - end,
+            _marker: core::marker::PhantomData,
+        }
+    }
 }
 
 impl<T> Drop for Vec<T> {
     fn drop(&mut self) {
-        while let Some(_) = self.pop() {}
-        unsafe {
-            free(self.ptr.as_ptr() as *mut _);
+        while self.pop().is_some() {}
+        if !Self::is_zst() && self.cap != 0 {
+            unsafe {
+                let layout = core::alloc::Layout::array::<T>(self.cap).unwrap();
+                raw_alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            }
         }
     }
 }
 
 impl<T> Vec<T> where T: Copy {
-    pub fn from_slice(other: &[T]) -> Result<Vec<T>, ()> {
+    pub fn from_slice(other: &[T]) -> Result<Vec<T>, TryReserveError> {
         let mut vec = Vec::with_len(other.len())?;
         vec.copy_from_slice(other);
         Ok(vec)
     }
 
-    pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), ()> {
+    pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), TryReserveError> {
         self.reserve(other.len())?;
         let len = self.len;
         self.len += other.len();
@@ -162,6 +481,66 @@ impl<T> Vec<T> where T: Copy {
     }
 }
 
+impl<T> Vec<T> where T: PartialEq {
+    pub fn dedup(&mut self) {
+        let len = self.len;
+        if len <= 1 {
+            return;
+        }
+
+        // If `T::eq` panics partway through, unwinding must not leave `self.len`
+        // covering slots we've already `drop_in_place`'d (double drop) or
+        // excluding slots we haven't inspected yet (leak). This guard fixes
+        // `self.len` up on the way out: it shifts whatever was never
+        // inspected down to close the gap left by the duplicates already
+        // dropped, then shrinks `len` to match. On the non-panicking path it
+        // is simply forgotten once `self.len` has been set directly.
+        struct FillGapOnDrop<'a, T> {
+            read: usize,
+            write: usize,
+            vec: &'a mut Vec<T>,
+        }
+
+        impl<'a, T> Drop for FillGapOnDrop<'a, T> {
+            fn drop(&mut self) {
+                unsafe {
+                    let ptr = self.vec.ptr.as_ptr();
+                    let len = self.vec.len;
+                    if self.read < len && self.read != self.write {
+                        let src = ptr.add(self.read);
+                        let dst = ptr.add(self.write);
+                        core::ptr::copy(src, dst, len - self.read);
+                    }
+                    self.vec.len = self.write + len - self.read;
+                }
+            }
+        }
+
+        let mut gap = FillGapOnDrop { read: 1, write: 1, vec: self };
+        let ptr = gap.vec.ptr.as_ptr();
+
+        unsafe {
+            while gap.read < len {
+                let read_ptr = ptr.add(gap.read);
+                let prev_ptr = ptr.add(gap.write - 1);
+                if *read_ptr == *prev_ptr {
+                    gap.read += 1;
+                    core::ptr::drop_in_place(read_ptr);
+                } else {
+                    if gap.read != gap.write {
+                        core::ptr::copy_nonoverlapping(read_ptr, ptr.add(gap.write), 1);
+                    }
+                    gap.write += 1;
+                    gap.read += 1;
+                }
+            }
+
+            gap.vec.len = gap.write;
+            core::mem::forget(gap);
+        }
+    }
+}
+
 impl<T> Index<usize> for Vec<T> {
     type Output = T;
 
@@ -184,7 +563,7 @@ impl<T> Deref for Vec<T> {
             &[]
         } else {
             unsafe {
-                std::slice::from_raw_parts(self.ptr.as_ptr(), self.len)
+                core::slice::from_raw_parts(self.ptr.as_ptr(), self.len)
             }
         }
     }
@@ -196,7 +575,7 @@ impl<T> DerefMut for Vec<T> {
             &mut []
         } else {
             unsafe {
-                std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len)
+                core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len)
             }
         }
     }
@@ -204,6 +583,7 @@ impl<T> DerefMut for Vec<T> {
 
 pub struct IntoIter<T> {
     buf: Unique<T>,
+    cap: usize,
     ptr: *mut T,
     end: *mut T,
 }
@@ -214,31 +594,92 @@ impl<T> IntoIterator for Vec<T> {
 
     fn into_iter(self) -> IntoIter<T> {
         let buf = Unique::new(self.ptr.as_ptr());
+        let cap = self.cap;
         let ptr = buf.as_ptr();
-        let end = unsafe { buf.as_ptr().offset(self.len() as isize) };
-        std::mem::forget(self);
+        // For ZSTs, `offset` would add zero bytes regardless of `len`, so `end`
+        // would collapse onto `ptr` and the iterator would yield nothing. Treat
+        // the pointer as a plain counter instead, matching `Iterator::next` below.
+        let end = if Vec::<T>::is_zst() {
+            (ptr as usize).wrapping_add(self.len()) as *mut T
+        } else {
+            unsafe { ptr.add(self.len()) }
+        };
+        core::mem::forget(self);
         IntoIter {
-            buf: buf,
-            ptr: ptr,
-            end: end,
+            buf,
+            cap,
+            ptr,
+            end,
         }
     }
 }
 
 impl<T> Drop for IntoIter<T> {
     fn drop(&mut self) {
-        while let Some(_) = self.next() {}
-        unsafe {
-            free(self.buf.as_ptr() as *mut _);
+        for _ in self.by_ref() {}
+        if !Vec::<T>::is_zst() && self.cap != 0 {
+            unsafe {
+                let layout = core::alloc::Layout::array::<T>(self.cap).unwrap();
+                raw_alloc::dealloc(self.buf.as_ptr() as *mut u8, layout);
+            }
+        }
+    }
+}
+
+pub struct Drain<'a, T: 'a> {
+    vec: *mut Vec<T>,
+    iter: *const T,
+    end: *const T,
+    tail_start: usize,
+    tail_len: usize,
+    _marker: core::marker::PhantomData<&'a mut Vec<T>>,
+}
+
+impl<'a, T> Iterator for Drain<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.iter == self.end {
+            None
+        } else {
+            unsafe {
+                let result = core::ptr::read(self.iter);
+                self.iter = if Vec::<T>::is_zst() {
+                    (self.iter as usize + 1) as *const T
+                } else {
+                    self.iter.offset(1)
+                };
+                Some(result)
+            }
+        }
+    }
+}
+
+impl<'a, T> Drop for Drain<'a, T> {
+    fn drop(&mut self) {
+        // Drop whatever the caller didn't consume...
+        for _ in &mut *self {}
+        // ...then shift the surviving tail down to close the gap.
+        if self.tail_len > 0 {
+            unsafe {
+                let vec = &mut *self.vec;
+                let start = vec.len;
+                let src = vec.ptr.as_ptr().add(self.tail_start);
+                let dst = vec.ptr.as_ptr().add(start);
+                if src != dst {
+                    core::ptr::copy(src, dst, self.tail_len);
+                }
+                vec.len = start + self.tail_len;
+            }
         }
     }
 }
 
 impl<'a, T> IntoIterator for &'a Vec<T> {
     type Item = &'a T;
-    type IntoIter = std::slice::Iter<'a, T>;
+    type IntoIter = core::slice::Iter<'a, T>;
 
-    fn into_iter(self) -> std::slice::Iter<'a, T> {
+    fn into_iter(self) -> core::slice::Iter<'a, T> {
         self.iter()
     }
 }
@@ -252,28 +693,34 @@ impl<T> Iterator for IntoIter<T> {
         } else {
             unsafe {
                 let ptr = self.ptr;
-                self.ptr = self.ptr.offset(1);
-                Some(std::ptr::read(ptr))
+                self.ptr = if Vec::<T>::is_zst() {
+                    (self.ptr as usize + 1) as *mut T
+                } else {
+                    self.ptr.offset(1)
+                };
+                Some(core::ptr::read(ptr))
             }
         }
     }
 }
 
-fn oom() -> std::io::Error {
-    std::io::Error::new(std::io::ErrorKind::Other, "OOM")
+#[cfg(feature = "std")]
+fn oom(err: TryReserveError) -> std::io::Error {
+    std::io::Error::other(err)
 }
 
+#[cfg(feature = "std")]
 impl std::io::Write for Vec<u8> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        if self.extend_from_slice(buf).is_err() {
-            return Err(oom());
+        if let Err(err) = self.extend_from_slice(buf) {
+            return Err(oom(err));
         }
         Ok(buf.len())
     }
 
     fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
-        if self.extend_from_slice(buf).is_err() {
-            return Err(oom());
+        if let Err(err) = self.extend_from_slice(buf) {
+            return Err(oom(err));
         }
         Ok(())
     }
@@ -283,13 +730,13 @@ impl std::io::Write for Vec<u8> {
     }
 }
 
-impl<T> std::default::Default for Vec<T> {
+impl<T> core::default::Default for Vec<T> {
     fn default() -> Vec<T> {
         Vec::new()
     }
 }
 
-impl<T> std::clone::Clone for Vec<T> where T: Clone {
+impl<T> core::clone::Clone for Vec<T> where T: Clone {
     fn clone(&self) -> Vec<T> {
         // XXX: We can't return a result here, so just panic.
         let mut vec = Vec::new();
@@ -300,19 +747,19 @@ impl<T> std::clone::Clone for Vec<T> where T: Clone {
     }
 }
 
-impl<T> std::cmp::PartialEq for Vec<T> where T: PartialEq {
+impl<T> core::cmp::PartialEq for Vec<T> where T: PartialEq {
     fn eq(&self, other: &Vec<T>) -> bool {
         self.as_slice() == other.as_slice()
     }
 }
 
-impl<T> std::cmp::PartialEq<std::vec::Vec<T>> for Vec<T> where T: PartialEq {
-    fn eq(&self, other: &std::vec::Vec<T>) -> bool {
+impl<T> core::cmp::PartialEq<alloc::vec::Vec<T>> for Vec<T> where T: PartialEq {
+    fn eq(&self, other: &alloc::vec::Vec<T>) -> bool {
         self.as_slice() == other.as_slice()
     }
 }
 
-impl<'a, T> std::cmp::PartialEq<&'a [T]> for Vec<T> where T: PartialEq {
+impl<'a, T> core::cmp::PartialEq<&'a [T]> for Vec<T> where T: PartialEq {
     fn eq(&self, other: &&'a [T]) -> bool {
         self.as_slice() == *other
     }
@@ -320,7 +767,158 @@ impl<'a, T> std::cmp::PartialEq<&'a [T]> for Vec<T> where T: PartialEq {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     #[test]
     fn it_works() {
     }
+
+    #[test]
+    fn push_grows_capacity_by_doubling() {
+        let mut v = Vec::new();
+        assert_eq!(v.cap, 0);
+        v.push(1u32).unwrap();
+        assert_eq!(v.cap, 1);
+        v.push(2).unwrap();
+        assert_eq!(v.cap, 2);
+        v.push(3).unwrap();
+        assert_eq!(v.cap, 4);
+        v.push(4).unwrap();
+        assert_eq!(v.cap, 4);
+        v.push(5).unwrap();
+        assert_eq!(v.cap, 8);
+    }
+
+    #[test]
+    fn reserve_overflow_returns_capacity_overflow_error() {
+        let mut v: Vec<u8> = Vec::new();
+        assert_eq!(v.reserve(usize::MAX).unwrap_err(), TryReserveError::CapacityOverflow);
+    }
+
+    #[test]
+    fn with_len_zero_does_not_allocate_or_leak() {
+        let v: Vec<u8> = Vec::with_len(0).unwrap();
+        assert_eq!(v.cap, 0);
+        assert_eq!(v.len(), 0);
+        drop(v);
+
+        let v: Vec<u8> = Vec::from_slice(&[]).unwrap();
+        assert_eq!(v.len(), 0);
+    }
+
+    #[test]
+    fn insert_remove_swap_remove() {
+        let mut v = Vec::new();
+        for i in 0..5 {
+            v.push(i).unwrap();
+        }
+        v.insert(2, 99).unwrap();
+        assert_eq!(v.as_slice(), &[0, 1, 99, 2, 3, 4]);
+        assert_eq!(v.remove(2), 99);
+        assert_eq!(v.as_slice(), &[0, 1, 2, 3, 4]);
+        assert_eq!(v.swap_remove(1), 1);
+        assert_eq!(v.as_slice(), &[0, 4, 2, 3]);
+    }
+
+    #[test]
+    fn retain_keeps_matching_elements_in_order() {
+        let mut v = Vec::new();
+        for i in 0..10 {
+            v.push(i).unwrap();
+        }
+        v.retain(|&x| x % 2 == 0);
+        assert_eq!(v.as_slice(), &[0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn dedup_collapses_consecutive_duplicates() {
+        let mut v = Vec::new();
+        for &x in &[1, 1, 2, 3, 3, 3, 4] {
+            v.push(x).unwrap();
+        }
+        v.dedup();
+        assert_eq!(v.as_slice(), &[1, 2, 3, 4]);
+    }
+
+    // An iterator whose `size_hint` always undercounts, so `extend` has to
+    // fall back to its incremental re-reserve path instead of the bulk one.
+    struct Undercount<I>(I);
+
+    impl<I: Iterator> Iterator for Undercount<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<I::Item> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (0, None)
+        }
+    }
+
+    #[test]
+    fn extend_grows_past_an_undercounted_size_hint() {
+        let mut v = Vec::new();
+        v.extend(Undercount(0..50)).unwrap();
+        assert_eq!(v.len(), 50);
+        for i in 0..50 {
+            assert_eq!(v[i], i);
+        }
+    }
+
+    #[test]
+    fn forgotten_drain_truncates_without_exposing_stale_elements() {
+        let mut v = Vec::new();
+        for i in 0..5 {
+            v.push(i).unwrap();
+        }
+        std::mem::forget(v.drain(1..4));
+        assert_eq!(v.len(), 1);
+        assert_eq!(v.as_slice(), &[0]);
+    }
+
+    // Zero-sized but still droppable, so push/pop/drain round trips can prove
+    // destructors actually ran (or didn't) the same way a non-ZST's would.
+    struct ZstDrop;
+
+    static ZST_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    impl Drop for ZstDrop {
+        fn drop(&mut self) {
+            ZST_DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn zst_push_pop_and_drain_run_destructors_exactly_once() {
+        ZST_DROPS.store(0, Ordering::SeqCst);
+
+        let mut v = Vec::new();
+        for _ in 0..5 {
+            v.push(ZstDrop).unwrap();
+        }
+        assert_eq!(v.len(), 5);
+
+        assert!(v.pop().is_some());
+        assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 1);
+
+        {
+            let drained: std::vec::Vec<_> = v.drain(0..2).collect();
+            assert_eq!(drained.len(), 2);
+        }
+        assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 3);
+        assert_eq!(v.len(), 2);
+
+        drop(v);
+        assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn spare_capacity_mut_on_an_unallocated_vec_is_an_empty_slice() {
+        // A fresh non-ZST `Vec` has `cap == 0` and no allocation backing it;
+        // this must not hand back a slice built from a null pointer.
+        let mut v: Vec<u8> = Vec::new();
+        assert_eq!(v.spare_capacity_mut().len(), 0);
+    }
 }